@@ -12,7 +12,8 @@
 //!
 //! - The resulting collection is immutable
 //! - Construction is time consuming
-//! - Stores strings with a maximum length of `u8::MAX` **bytes**
+//! - Stores strings with a maximum length of `L::MAX` **bytes**, where
+//!   `L` defaults to `u8` but can be widened (see [`SubStr`])
 //! - Compression dependent on input (might be small)
 //! - Access is slower compared to a Vec
 //!
@@ -22,7 +23,7 @@
 
 use aho_corasick::AhoCorasick;
 use derive_more::From;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -32,14 +33,33 @@ use serde::{Deserialize, Serialize};
 /// done using [`Builder`]. Once created no new elements can be added
 /// or changed. Individual elements can be accessed using `get()` or
 /// get an Iterator over the elements using iter().
+///
+/// Building with [`Builder::with_index`] enabled also populates a
+/// reverse lookup table, turning the collection into a compact set
+/// or interner: `position()` and `contains()` let you go from a
+/// `&str` back to its index in O(1) instead of scanning every element.
+///
+/// `SubStr` is generic over the span-length type `L`, which defaults
+/// to `u8`. Elements longer than `L::MAX` bytes cannot be stored; pick
+/// `u16` or `u32` via `SubStr<u16>` / `Builder<u16>` etc. for corpora
+/// with longer entries, at the cost of a larger span table.
+///
+/// [`to_bytes`](Self::to_bytes)/[`from_bytes`](Self::from_bytes) (de)serialize
+/// to a compact binary layout independent of `serde`. [`SubStrRef`] reads
+/// that same layout directly off a borrowed byte slice (e.g. a
+/// memory-mapped file) with a single validation pass and no allocation.
 #[derive(Clone, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct SubStr {
-    pub spans: Vec<(u32, u8)>,
+pub struct SubStr<L = u8> {
+    pub spans: Vec<(u32, L)>,
     pub string: String,
+    index: Option<HashMap<Box<str>, u32>>,
 }
 
-impl SubStr {
+impl<L> SubStr<L>
+where
+    L: Into<u64> + Copy,
+{
     /// Returns the number of elements in the substring vector, also referred to as its ‘length’.
     pub fn len(&self) -> usize {
         self.spans.len()
@@ -58,16 +78,18 @@ impl SubStr {
     /// Return the `&str` at `index` if the element
     /// exists.
     pub fn get(&self, index: usize) -> Option<&str> {
-        (index < self.len()).then_some(
-            &self.string[self.spans[index].0 as usize
-                ..self.spans[index].0 as usize + self.spans[index].1 as usize],
-        )
+        let (position, length) = *self.spans.get(index)?;
+        let position = position as usize;
+        let length: u64 = length.into();
+        let length = length as usize;
+        Some(&self.string[position..position + length])
     }
 
     /// Returns an iterator over the collection.
-    pub fn iter<'a>(&'a self) -> Iter<'a> {
+    pub fn iter<'a>(&'a self) -> Iter<'a, L> {
         Iter {
             current_item: 0,
+            end: self.len(),
             vec: &self,
         }
     }
@@ -90,7 +112,8 @@ impl SubStr {
     pub fn after(&self, index: usize, len: usize) -> Option<&str> {
         if let Some((position, length)) = self.spans.get(index) {
             let position = *position as usize;
-            let length = *length as usize;
+            let length: u64 = (*length).into();
+            let length = length as usize;
             let mut end = if self.string.len() <= position + length + len {
                 self.string.len()
             } else {
@@ -104,53 +127,254 @@ impl SubStr {
             None
         }
     }
+
+    /// Returns the index of `s` in the collection, if present.
+    ///
+    /// Only returns `Some` if the collection was built with
+    /// [`Builder::with_index`] enabled; otherwise always returns `None`.
+    pub fn position(&self, s: &str) -> Option<usize> {
+        self.index.as_ref()?.get(s).map(|&i| i as usize)
+    }
+
+    /// Returns `true` if the collection contains `s`.
+    ///
+    /// Only meaningful if the collection was built with
+    /// [`Builder::with_index`] enabled; otherwise always returns `false`.
+    pub fn contains(&self, s: &str) -> bool {
+        self.index.as_ref().is_some_and(|index| index.contains_key(s))
+    }
+
+    /// Serialize into a compact binary layout: an 8-byte header (element
+    /// count, storage length, both little-endian `u32`), the span table
+    /// as little-endian `(u32, u64)` records, then the UTF-8 storage
+    /// bytes. Round-trips through [`SubStr::from_bytes`] or can be read
+    /// without allocation via [`SubStrRef::from_bytes`].
+    ///
+    /// The lookup index built by [`Builder::with_index`] is not
+    /// serialized; a `SubStr` loaded from bytes always has `position()`
+    /// and `contains()` return `None`/`false`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        debug_assert!(
+            u32::try_from(self.spans.len()).is_ok() && u32::try_from(self.string.len()).is_ok(),
+            "element count and storage length must fit in u32 to round-trip through the binary header"
+        );
+        let mut out = Vec::with_capacity(
+            HEADER_LEN + self.spans.len() * SPAN_RECORD_LEN + self.string.len(),
+        );
+        out.extend_from_slice(&(self.spans.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(self.string.len() as u32).to_le_bytes());
+        for &(position, length) in &self.spans {
+            out.extend_from_slice(&position.to_le_bytes());
+            let length: u64 = length.into();
+            out.extend_from_slice(&length.to_le_bytes());
+        }
+        out.extend_from_slice(self.string.as_bytes());
+        out
+    }
+}
+
+impl<L> SubStr<L>
+where
+    L: TryFrom<u64> + Into<u64> + Copy,
+{
+    /// Deserialize a [`SubStr`] previously written with
+    /// [`SubStr::to_bytes`].
+    ///
+    /// Validates that `bytes` is large enough to hold the declared span
+    /// table and storage region, that the storage bytes are valid UTF-8,
+    /// and that every span lies on a char boundary within the storage,
+    /// before returning the `SubStr`. The storage bytes are copied into
+    /// the owned `String` before the spans are checked.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (count, span_bytes, storage) = split_bytes(bytes)?;
+        let string = String::from_utf8(storage.to_vec())?;
+
+        let mut spans = Vec::with_capacity(count);
+        for i in 0..count {
+            let (position, length) = decode_span(span_bytes, i);
+            validate_span(&string, i, position, length)?;
+            let span_len =
+                L::try_from(length).map_err(|_| Error::StringTooLong(length as usize))?;
+            spans.push((position, span_len));
+        }
+
+        Ok(Self {
+            spans,
+            string,
+            index: None,
+        })
+    }
+}
+
+/// A borrowed, read-only view over the binary layout written by
+/// [`SubStr::to_bytes`], built directly over `bytes` (e.g. a
+/// memory-mapped file) with a single validation pass and no allocation.
+///
+/// Unlike [`SubStr`], `SubStrRef` is not generic over a span-length
+/// type: spans are decoded from the on-disk `u64` length field on
+/// access, so one borrowed view works for bytes produced from any
+/// `SubStr<L>`.
+pub struct SubStrRef<'a> {
+    spans: &'a [u8],
+    count: usize,
+    string: &'a str,
+}
+
+impl<'a> SubStrRef<'a> {
+    /// Construct a view over `bytes`, validating the header, span
+    /// table and storage region are present, that the storage is valid
+    /// UTF-8, and that every span lies on a char boundary within it.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self> {
+        let (count, span_bytes, storage) = split_bytes(bytes)?;
+        let string = std::str::from_utf8(storage)?;
+        for i in 0..count {
+            let (position, length) = decode_span(span_bytes, i);
+            validate_span(string, i, position, length)?;
+        }
+        Ok(Self {
+            spans: span_bytes,
+            count,
+            string,
+        })
+    }
+
+    /// Returns the number of elements in the collection.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Returns `true` if the collection contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the length of the storage string in bytes.
+    pub fn storage_len(&self) -> usize {
+        self.string.len()
+    }
+
+    /// Return the `&str` at `index` if the element exists.
+    pub fn get(&self, index: usize) -> Option<&'a str> {
+        if index >= self.count {
+            return None;
+        }
+        let (position, length) = decode_span(self.spans, index);
+        let position = position as usize;
+        let length = length as usize;
+        Some(&self.string[position..position + length])
+    }
+}
+
+impl<'a> std::ops::Index<usize> for SubStrRef<'a> {
+    type Output = str;
+
+    /// Returns the `&str` at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<L> std::ops::Index<usize> for SubStr<L>
+where
+    L: Into<u64> + Copy,
+{
+    type Output = str;
+
+    /// Returns the `&str` at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index out of bounds")
+    }
 }
 
-pub struct Iter<'a> {
+pub struct Iter<'a, L = u8> {
     current_item: usize,
-    vec: &'a SubStr,
+    end: usize,
+    vec: &'a SubStr<L>,
 }
 
-impl<'a> Iterator for Iter<'a> {
+impl<'a, L> Iterator for Iter<'a, L>
+where
+    L: Into<u64> + Copy,
+{
     type Item = &'a str;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current_item < self.vec.len() {
+        if self.current_item < self.end {
             self.current_item += 1;
             self.vec.get(self.current_item - 1)
         } else {
             None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.current_item;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, L> DoubleEndedIterator for Iter<'a, L>
+where
+    L: Into<u64> + Copy,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.current_item < self.end {
+            self.end -= 1;
+            self.vec.get(self.end)
+        } else {
+            None
+        }
+    }
 }
 
+impl<'a, L> ExactSizeIterator for Iter<'a, L> where L: Into<u64> + Copy {}
+
+impl<'a, L> std::iter::FusedIterator for Iter<'a, L> where L: Into<u64> + Copy {}
+
 /// A [`SubStr`] builder.
 ///
 /// You can turn a `Vec<String>` into a `Builder` using `TryFrom`
 /// or from something that can be turned into an `Iterator`
 /// over  anything that can be turned into a `&str` using [`from_iter()`].
 ///
-/// You can construct a [`SubStr`] or [`SubStrMap`] using the [`build_substr()`]
-/// or [`build_substr_map()`] methods. If you want to verify the result
-/// use the [`verify()`] method before construction. The build process
-/// can take a long time, use [`messages()`] to show progress on stdout.
+/// You can construct a [`SubStr`] using [`build()`](Self::build) or, for
+/// denser (but slower) compression, [`build_greedy()`](Self::build_greedy).
+/// If you want to verify the result use [`verify()`](Self::verify) before
+/// construction. The build process can take a long time, use
+/// [`debug_messages()`](Self::debug_messages) to show progress on stdout.
+///
+/// `Builder` is generic over the span-length type `L` (default `u8`),
+/// matching [`SubStr<L>`]. Use `Builder::<u16>::from_iter(...)` etc. to
+/// raise the per-element length limit above `u8::MAX` bytes.
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Builder {
+pub struct Builder<L = u8> {
     vec: Vec<String>,
-    contained_in: Vec<Option<(u32, u8)>>,
+    contained_in: Vec<Option<(u32, L)>>,
     index_string: String,
-    spans: Vec<Option<(u32, u8)>>,
+    spans: Vec<Option<(u32, L)>>,
     silent: bool,
     build: bool,
+    with_index: bool,
 }
 
-impl TryFrom<Vec<String>> for Builder {
+impl<L> TryFrom<Vec<String>> for Builder<L>
+where
+    L: TryFrom<usize> + Copy,
+{
     type Error = Error;
 
     fn try_from(value: Vec<String>) -> Result<Self> {
         let max_len = value.iter().map(|s| s.len()).max().unwrap();
-        if max_len > u8::MAX as usize {
+        if L::try_from(max_len).is_err() {
             return Err(Error::StringTooLong(max_len));
         }
         Ok(Self {
@@ -159,17 +383,21 @@ impl TryFrom<Vec<String>> for Builder {
             index_string: String::new(),
             silent: true,
             build: false,
+            with_index: false,
             vec: value,
         })
     }
 }
 
-impl Builder {
+impl<L> Builder<L>
+where
+    L: TryFrom<usize> + Into<u64> + Copy,
+{
     /// Create a `SubStr` `Builder` from an Iterator. This
     /// method checks the length of the strings added, and fails
     /// when it it contains a string that is to long. (This
     /// is why `Builder` doesn't implement `FromIterator` )
-    pub fn from_iter<I, S>(iter: I) -> Result<Builder>
+    pub fn from_iter<I, S>(iter: I) -> Result<Builder<L>>
     where
         S: AsRef<str>,
         I: IntoIterator<Item = S>,
@@ -181,7 +409,7 @@ impl Builder {
             .map(|s| s.len())
             .max()
             .ok_or(Error::NoMaxStringLen)?;
-        if max_len > u8::MAX as usize {
+        if L::try_from(max_len).is_err() {
             return Err(crate::Error::StringTooLong(max_len));
         }
 
@@ -191,6 +419,7 @@ impl Builder {
             index_string: String::new(),
             silent: true,
             build: false,
+            with_index: false,
             vec,
         })
     }
@@ -199,6 +428,12 @@ impl Builder {
         self.silent = !on;
     }
 
+    /// Enable building a reverse lookup index, so that the resulting
+    /// [`SubStr`] supports `position()` and `contains()`.
+    pub fn with_index(&mut self, on: bool) {
+        self.with_index = on;
+    }
+
     pub fn build_only(&mut self) -> Result<()> {
         if self.build {
             return Ok(());
@@ -207,22 +442,22 @@ impl Builder {
         if !self.silent {
             println!("1/4 -> Looking for substrings ...");
         }
-        self.find_substrings();
+        self.find_substrings()?;
 
         if !self.silent {
             println!("2/4 -> Looking for partial substrings ...");
         }
-        self.find_partial_substrings();
+        self.find_partial_substrings()?;
 
         if !self.silent {
             println!("3/4 -> Adding uncontained strings ...");
         }
-        self.join_loose_strings();
+        self.join_loose_strings()?;
 
         if !self.silent {
             println!("4/4 -> Adding substrings ...");
         }
-        self.join_substrings();
+        self.join_substrings()?;
         if !self.silent {
             println!("    -> Finished");
         }
@@ -230,13 +465,67 @@ impl Builder {
         Ok(())
     }
 
-    pub fn build(mut self) -> Result<SubStr> {
+    /// Like [`build_only`](Self::build_only), but merges the uncontained
+    /// strings using [`find_partial_substrings_greedy`](Self::find_partial_substrings_greedy)
+    /// instead of [`find_partial_substrings`](Self::find_partial_substrings).
+    fn build_only_greedy(&mut self) -> Result<()> {
+        if self.build {
+            return Ok(());
+        }
+
+        if !self.silent {
+            println!("1/4 -> Looking for substrings ...");
+        }
+        self.find_substrings()?;
+
+        if !self.silent {
+            println!("2/4 -> Merging overlapping substrings ...");
+        }
+        self.find_partial_substrings_greedy()?;
+
+        if !self.silent {
+            println!("3/4 -> Adding uncontained strings ...");
+        }
+        self.join_loose_strings()?;
+
+        if !self.silent {
+            println!("4/4 -> Adding substrings ...");
+        }
+        self.join_substrings()?;
+        if !self.silent {
+            println!("    -> Finished");
+        }
+        self.build = true;
+        Ok(())
+    }
+
+    /// Build a [`SubStr`], approximating the shortest common superstring
+    /// problem instead of `build()`'s single left-to-right greedy scan:
+    /// repeatedly merges the pair of remaining uncontained strings with
+    /// the largest suffix/prefix overlap. Produces a denser `index_string`
+    /// at the cost of a longer build.
+    pub fn build_greedy(mut self) -> Result<SubStr<L>> {
+        if !self.build {
+            self.build_only_greedy()?;
+        }
+        self.build()
+    }
+
+    pub fn build(mut self) -> Result<SubStr<L>> {
         if !self.build {
             self.build_only()?;
         }
+        let index = self.with_index.then(|| {
+            self.vec
+                .iter()
+                .enumerate()
+                .map(|(i, s)| (s.as_str().into(), i as u32))
+                .collect()
+        });
         Ok(SubStr {
             string: self.index_string,
             spans: self.spans.iter().map(|s| s.unwrap()).collect(),
+            index,
         })
     }
 
@@ -247,6 +536,7 @@ impl Builder {
         }
         for (i, w) in self.vec.iter().enumerate() {
             if let Some((b, e)) = self.spans[i] {
+                let e: u64 = e.into();
                 if w != &self.index_string[b as usize..(b as usize + e as usize)] {
                     self.debug(i);
                     return Ok(false);
@@ -258,22 +548,23 @@ impl Builder {
 
     // 1/4 of building
     // Find strings that are substrings of other strings.
-    fn find_substrings(&mut self) {
+    fn find_substrings(&mut self) -> Result<()> {
         let ac = AhoCorasick::new(self.vec.iter().map(String::as_str)).unwrap();
         for (i, w) in self.vec.iter().enumerate() {
             for mat in ac.find_overlapping_iter(&w) {
                 let index = mat.pattern().as_usize();
-                let start = mat.start() as u8;
+                let start = to_len(mat.start())?;
                 if index != i && self.contained_in[index].is_none() {
                     self.contained_in[index] = Some((i as u32, start));
                 }
             }
         }
+        Ok(())
     }
 
     // 1/4 of building
     // find strings that match the end of the storage string
-    fn find_partial_substrings(&mut self) {
+    fn find_partial_substrings(&mut self) -> Result<()> {
         if !self.silent {
             println!("    -> make hashmap ...");
         }
@@ -311,17 +602,18 @@ impl Builder {
             if self.spans[index].is_some() {
                 continue;
             }
-            self.spans[index] = Some((position as u32, string.len() as u8));
+            self.spans[index] = Some((position as u32, to_len(string.len())?));
             self.index_string.push_str(string);
             position += string.len();
             while let Some(next) = self.find_next_string(index, position, &beginnings) {
                 self.spans[next.index] =
-                    Some((next.position as u32, self.vec[next.index].len() as u8));
+                    Some((next.position as u32, to_len(self.vec[next.index].len())?));
                 self.index_string.push_str(&next.tail);
                 index = next.index;
                 position = next.position + self.vec[next.index].len();
             }
         }
+        Ok(())
     }
 
     fn find_next_string(
@@ -350,42 +642,173 @@ impl Builder {
         None
     }
 
+    // 2/4 of building (greedy build mode)
+    // Approximate the shortest common superstring problem: repeatedly
+    // merge the pair of remaining uncontained strings with the largest
+    // suffix/prefix overlap, rather than find_partial_substrings'
+    // single left-to-right greedy scan. Candidate pairs are bucketed by
+    // a short fixed-length key (the same idea as `beginnings` above) so
+    // only plausibly-overlapping pairs are scored.
+    fn find_partial_substrings_greedy(&mut self) -> Result<()> {
+        if !self.silent {
+            println!("    -> computing overlaps ...");
+        }
+
+        let mut fragments: Vec<Fragment> = self
+            .vec
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.contained_in[*i].is_none())
+            .map(|(i, w)| Fragment {
+                text: w.clone(),
+                parts: vec![(i as u32, 0)],
+            })
+            .collect();
+
+        while fragments.len() > 1 {
+            let mut beginnings: HashMap<&str, Vec<usize>> = HashMap::new();
+            for (idx, fragment) in fragments.iter().enumerate() {
+                beginnings
+                    .entry(overlap_key(&fragment.text))
+                    .or_insert(Vec::new())
+                    .push(idx);
+            }
+
+            let mut best: Option<(usize, usize, usize)> = None;
+            for (i, fragment) in fragments.iter().enumerate() {
+                for (start, _) in fragment.text.char_indices() {
+                    if start == 0 {
+                        continue;
+                    }
+                    let suffix = &fragment.text[start..];
+                    if suffix.chars().count() < OVERLAP_KEY_LEN {
+                        break;
+                    }
+                    let Some(candidates) = beginnings.get(overlap_key(suffix)) else {
+                        continue;
+                    };
+                    let found = candidates.iter().find(|&&j| {
+                        j != i
+                            && suffix.len() <= fragments[j].text.len()
+                            && fragments[j].text.as_bytes()[..suffix.len()] == *suffix.as_bytes()
+                    });
+                    if let Some(&j) = found {
+                        let overlap = suffix.len();
+                        if best.is_none_or(|(_, _, best_overlap)| overlap > best_overlap) {
+                            best = Some((i, j, overlap));
+                        }
+                        break;
+                    }
+                }
+            }
+
+            let Some((i, j, overlap)) = best else {
+                break;
+            };
+
+            let (a, b) = if i < j {
+                let b = fragments.remove(j);
+                let a = fragments.remove(i);
+                (a, b)
+            } else {
+                let a = fragments.remove(i);
+                let b = fragments.remove(j);
+                (a, b)
+            };
+
+            let mut parts = a.parts;
+            let offset = a.text.len() as u32 - overlap as u32;
+            parts.extend(b.parts.iter().map(|(index, pos)| (*index, pos + offset)));
+            let mut text = a.text;
+            text.push_str(&b.text[overlap..]);
+            fragments.push(Fragment { text, parts });
+        }
+
+        for fragment in fragments {
+            let base = self.index_string.len() as u32;
+            for (index, offset) in fragment.parts {
+                self.spans[index as usize] =
+                    Some((base + offset, to_len(self.vec[index as usize].len())?));
+            }
+            self.index_string.push_str(&fragment.text);
+        }
+        Ok(())
+    }
+
     // 3/4 of building
     // Add the strings that are no substrings
-    fn join_loose_strings(&mut self) {
+    fn join_loose_strings(&mut self) -> Result<()> {
         for (i, w) in self.vec.iter_mut().enumerate() {
             if self.contained_in[i].is_none() && self.spans[i].is_none() {
-                self.spans[i] = Some((self.index_string.len() as u32, w.len() as u8));
+                self.spans[i] = Some((self.index_string.len() as u32, to_len(w.len())?));
                 self.index_string.push_str(w);
             }
         }
+        Ok(())
     }
 
     // 4/4 of building
     // Add the substrings.
-    fn join_substrings(&mut self) {
-        while self.spans.iter().filter(|s| s.is_none()).count() > 0 {
-            for (i, (cid, start)) in self.contained_in.iter().enumerate().filter_map(|(i, o)| {
-                if o.is_some() {
-                    Some((i, o.unwrap()))
-                } else {
-                    None
-                }
-            }) {
-                if self.spans[i].is_none() {
-                    if let Some((container_pos, _)) = self.spans[cid as usize] {
-                        self.spans[i] =
-                            Some((container_pos + (start as u32), self.vec[i].len() as u8));
-                    }
-                }
+    //
+    // Each unresolved element `i` points at its container via
+    // `contained_in[i] = (parent, start)`. Follow that chain up to the
+    // first ancestor that already has a concrete span (`join_loose_strings`
+    // anchored every non-contained string; a chain that loops back on
+    // itself without reaching one, as identical strings produce, is
+    // reported as `Error::CyclicContainment`),
+    // summing offsets along the way, then resolve every element on the
+    // chain in one pass so shared ancestors are only walked once.
+    fn join_substrings(&mut self) -> Result<()> {
+        for i in 0..self.spans.len() {
+            self.resolve_span(i)?;
+        }
+        Ok(())
+    }
+
+    // Walk the `contained_in` chain from `start_index` up to the first
+    // ancestor with a concrete span, recording the path in `seen`/`path`
+    // so a cycle (of any length, not just a direct self-reference)
+    // surfaces as `Error::CyclicContainment` instead of recursing
+    // forever; the walk is iterative, so a pathological chain cannot
+    // overflow the stack either. Every element on the path is then
+    // resolved in one pass, so shared ancestors are only walked once.
+    fn resolve_span(&mut self, start_index: usize) -> Result<(u32, L)> {
+        if let Some(span) = self.spans[start_index] {
+            return Ok(span);
+        }
+
+        let mut path = vec![start_index];
+        let mut seen: HashSet<usize> = HashSet::from([start_index]);
+        let mut current = start_index;
+        let root_pos = loop {
+            if let Some((position, _)) = self.spans[current] {
+                break position;
+            }
+            let (parent, _) = self.contained_in[current]
+                .expect("join_loose_strings must anchor every non-contained string");
+            let parent = parent as usize;
+            if !seen.insert(parent) {
+                return Err(Error::CyclicContainment(parent));
             }
+            path.push(parent);
+            current = parent;
+        };
+
+        let mut position = root_pos;
+        for &i in path.iter().rev().skip(1) {
+            let (_, start) = self.contained_in[i].expect("checked while walking the chain above");
+            let start: u64 = start.into();
+            position += start as u32;
+            self.spans[i] = Some((position, to_len(self.vec[i].len())?));
         }
+        Ok(self.spans[start_index].expect("resolved by the loop above"))
     }
 
     fn debug(&self, id: usize) {
         print!("{} [{}]", self.vec[id], id);
         if let Some((s, l)) = self.spans[id] {
             let s = s as usize;
+            let l: u64 = l.into();
             let l = l as usize;
             let mut bss = if s < 10 { 0 } else { s - 10 };
             let mut ess = if self.index_string.len() <= s + l + 10 {
@@ -421,6 +844,26 @@ struct NextStr {
     tail: String,
 }
 
+// A merged run of strings built up by `find_partial_substrings_greedy`,
+// tracking where each original string (by index into `Builder::vec`)
+// starts within `text`.
+struct Fragment {
+    text: String,
+    parts: Vec<(u32, u32)>,
+}
+
+// Number of leading chars used to bucket fragments by their prefix when
+// looking for overlap candidates; overlaps shorter than this are not
+// considered, trading a little compression for a much smaller search space.
+const OVERLAP_KEY_LEN: usize = 4;
+
+fn overlap_key(s: &str) -> &str {
+    match s.char_indices().nth(OVERLAP_KEY_LEN) {
+        Some((i, _)) => &s[..i],
+        None => s,
+    }
+}
+
 fn split_after_char(s: &str, after: usize) -> Option<(&str, &str)> {
     if after == 0 {
         return None;
@@ -431,6 +874,76 @@ fn split_after_char(s: &str, after: usize) -> Option<(&str, &str)> {
     }
 }
 
+// Fallibly narrow a byte length to the span-length type `L`, reporting
+// oversized elements as `Error::StringTooLong` instead of panicking or
+// silently truncating.
+fn to_len<L: TryFrom<usize>>(n: usize) -> Result<L> {
+    L::try_from(n).map_err(|_| Error::StringTooLong(n))
+}
+
+// Size in bytes of the `to_bytes`/`from_bytes` header: element count
+// followed by storage length, both little-endian `u32`.
+const HEADER_LEN: usize = 8;
+
+// Size in bytes of one span record in the binary layout: a little-endian
+// `u32` position followed by a little-endian `u64` length.
+const SPAN_RECORD_LEN: usize = 12;
+
+// Split a byte slice produced by `SubStr::to_bytes` into the element
+// count, the raw span table, and the raw storage bytes, checking only
+// that `bytes` is large enough to hold the regions its header declares.
+fn split_bytes(bytes: &[u8]) -> Result<(usize, &[u8], &[u8])> {
+    let header: [u8; HEADER_LEN] = bytes.get(..HEADER_LEN).unwrap_or(bytes).try_into()?;
+    let count = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+    let storage_len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+
+    // Use checked arithmetic throughout: `count`/`storage_len` come from
+    // the (possibly corrupt or malicious) input, so a header claiming an
+    // absurd size must fail with `InvalidData` rather than overflow or
+    // wrap into a bounds check that then lets `decode_span` index out of
+    // range.
+    let span_table_len = count
+        .checked_mul(SPAN_RECORD_LEN)
+        .ok_or(Error::InvalidData(HEADER_LEN))?;
+    let storage_start = HEADER_LEN
+        .checked_add(span_table_len)
+        .ok_or(Error::InvalidData(HEADER_LEN))?;
+    let span_bytes = bytes
+        .get(HEADER_LEN..storage_start)
+        .ok_or(Error::InvalidData(HEADER_LEN))?;
+
+    let storage_end = storage_start
+        .checked_add(storage_len)
+        .ok_or(Error::InvalidData(storage_start))?;
+    let storage = bytes
+        .get(storage_start..storage_end)
+        .ok_or(Error::InvalidData(storage_start))?;
+
+    Ok((count, span_bytes, storage))
+}
+
+// Decode the `index`-th span record out of a raw span table produced by
+// `split_bytes`.
+fn decode_span(span_bytes: &[u8], index: usize) -> (u32, u64) {
+    let base = index * SPAN_RECORD_LEN;
+    let position = u32::from_le_bytes(span_bytes[base..base + 4].try_into().unwrap());
+    let length = u64::from_le_bytes(span_bytes[base + 4..base + SPAN_RECORD_LEN].try_into().unwrap());
+    (position, length)
+}
+
+// Check that a decoded span lies on a char boundary within `string`,
+// surfacing malformed input as `Error::InvalidData(index)` instead of
+// panicking on the out-of-bounds slice in `get()`.
+fn validate_span(string: &str, index: usize, position: u32, length: u64) -> Result<()> {
+    let position = position as usize;
+    let length = length as usize;
+    let end = position.checked_add(length).ok_or(Error::InvalidData(index))?;
+    if end > string.len() || !string.is_char_boundary(position) || !string.is_char_boundary(end) {
+        return Err(Error::InvalidData(index));
+    }
+    Ok(())
+}
+
 pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug, From)]
@@ -439,12 +952,28 @@ pub enum Error {
 
     NoMaxStringLen,
 
+    /// A string's `contained_in` chain loops back on itself instead of
+    /// terminating at an ancestor anchored by `join_loose_strings`. The
+    /// field is the index at which the cycle was detected.
+    CyclicContainment(usize),
+
+    /// A span decoded from `from_bytes` is out of bounds, overflows, or
+    /// does not land on a char boundary within the storage string. The
+    /// field is the offending span's element index (or, for a
+    /// truncated span table/storage region, the byte offset where the
+    /// expected data is missing). A truncated header is reported as
+    /// `TryFromSliceError` instead.
+    InvalidData(usize),
+
     #[from]
     Io(std::io::Error),
 
     #[from]
     Utf8Error(std::string::FromUtf8Error),
 
+    #[from]
+    Utf8StrError(std::str::Utf8Error),
+
     #[from]
     TryFromSliceError(std::array::TryFromSliceError),
 }
@@ -457,13 +986,192 @@ impl core::fmt::Display for Error {
 
 impl core::error::Error for Error {}
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_and_contains_without_index_are_always_empty() {
+        let b = Builder::<u8>::from_iter(["hello", "world"]).unwrap();
+        let s = b.build().unwrap();
+        assert_eq!(s.position("hello"), None);
+        assert!(!s.contains("hello"));
+    }
 
-//     #[test]
-//     fn it_works() {
-//         let result = add(2, 2);
-//         assert_eq!(result, 4);
-//     }
-// }
+    #[test]
+    fn with_index_enables_position_and_contains() {
+        let words = ["hello", "world", "hell"];
+        let mut b = Builder::<u8>::from_iter(words).unwrap();
+        b.with_index(true);
+        let s = b.build().unwrap();
+        for (i, w) in words.iter().enumerate() {
+            assert_eq!(s.position(w), Some(i));
+            assert!(s.contains(w));
+        }
+        assert_eq!(s.position("nope"), None);
+        assert!(!s.contains("nope"));
+    }
+
+    #[test]
+    fn iter_is_double_ended_and_exact_size() {
+        let b = Builder::<u8>::from_iter(["alpha", "beta", "gamma"]).unwrap();
+        let s = b.build().unwrap();
+        let mut iter = s.iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next(), Some("alpha"));
+        assert_eq!(iter.next_back(), Some("gamma"));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next(), Some("beta"));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn index_mirrors_get() {
+        let b = Builder::<u8>::from_iter(["alpha", "beta"]).unwrap();
+        let s = b.build().unwrap();
+        assert_eq!(&s[0], "alpha");
+        assert_eq!(&s[1], "beta");
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn index_panics_out_of_bounds() {
+        let b = Builder::<u8>::from_iter(["alpha"]).unwrap();
+        let s = b.build().unwrap();
+        let _ = &s[5];
+    }
+
+    #[test]
+    fn element_longer_than_u8_max_is_rejected() {
+        let long = "x".repeat(300);
+        let err = Builder::<u8>::from_iter([long]).unwrap_err();
+        assert!(matches!(err, Error::StringTooLong(300)));
+    }
+
+    #[test]
+    fn widened_span_length_accepts_longer_elements() {
+        let long = "x".repeat(300);
+        let b = Builder::<u16>::from_iter([long.clone()]).unwrap();
+        let s = b.build().unwrap();
+        assert_eq!(s.get(0), Some(long.as_str()));
+    }
+
+    #[test]
+    fn join_substrings_resolves_a_multi_level_chain() {
+        // "a" is contained in "ba" is contained in "cba": a two-hop
+        // `contained_in` chain, exercising resolve_span beyond the
+        // single-parent case.
+        let words = ["a", "ba", "cba"];
+        let b = Builder::<u8>::from_iter(words).unwrap();
+        let s = b.build().unwrap();
+        for (i, w) in words.iter().enumerate() {
+            assert_eq!(s.get(i), Some(*w));
+        }
+        assert_eq!(s.storage_len(), 3);
+    }
+
+    #[test]
+    fn join_substrings_rejects_cyclic_containment() {
+        // Two identical strings each register the other as their
+        // container, producing a `contained_in` cycle with no anchored
+        // ancestor; this must surface as an error instead of recursing
+        // forever.
+        let b = Builder::<u8>::from_iter(["ab", "ab"]).unwrap();
+        assert!(matches!(b.build(), Err(Error::CyclicContainment(_))));
+    }
+
+    #[test]
+    fn build_greedy_preserves_every_element() {
+        let words = ["overlap", "lapping", "pingpong"];
+        let b = Builder::<u8>::from_iter(words).unwrap();
+        let s = b.build_greedy().unwrap();
+        for (i, w) in words.iter().enumerate() {
+            assert_eq!(s.get(i), Some(*w));
+        }
+    }
+
+    #[test]
+    fn build_greedy_merges_overlaps_more_densely_than_concatenation() {
+        // "conflict"/"flictions" share a 5-byte suffix/prefix overlap;
+        // the greedy SCS-overlap merge should find it and produce a
+        // storage string shorter than the naive concatenation.
+        let words = ["conflict", "flictions"];
+        let greedy = Builder::<u8>::from_iter(words)
+            .unwrap()
+            .build_greedy()
+            .unwrap();
+        assert!(greedy.storage_len() < words.iter().map(|w| w.len()).sum::<usize>());
+    }
+
+    fn built(words: &[&str]) -> SubStr<u8> {
+        let mut b = Builder::<u8>::from_iter(words.iter().copied()).unwrap();
+        b.with_index(true);
+        b.build().unwrap()
+    }
+
+    #[test]
+    fn to_bytes_round_trips_through_sub_str_from_bytes() {
+        let words = ["hello", "hell", "world", "orld"];
+        let s = built(&words);
+        let bytes = s.to_bytes();
+        let s2 = SubStr::<u8>::from_bytes(&bytes).unwrap();
+        assert_eq!(s.len(), s2.len());
+        for i in 0..s.len() {
+            assert_eq!(s.get(i), s2.get(i));
+        }
+        // The lookup index is not serialized.
+        assert!(!s2.contains("hello"));
+    }
+
+    #[test]
+    fn to_bytes_round_trips_through_sub_str_ref_from_bytes() {
+        let words = ["hello", "hell", "world", "orld"];
+        let s = built(&words);
+        let bytes = s.to_bytes();
+        let r = SubStrRef::from_bytes(&bytes).unwrap();
+        assert_eq!(r.len(), s.len());
+        assert_eq!(r.storage_len(), s.storage_len());
+        for i in 0..s.len() {
+            assert_eq!(s.get(i), r.get(i));
+        }
+        assert_eq!(&r[0], s.get(0).unwrap());
+    }
+
+    #[test]
+    fn from_bytes_rejects_truncated_input() {
+        let bytes = built(&["hello", "world"]).to_bytes();
+        for end in [0, 4, 7, bytes.len() - 1] {
+            assert!(SubStr::<u8>::from_bytes(&bytes[..end]).is_err());
+            assert!(SubStrRef::from_bytes(&bytes[..end]).is_err());
+        }
+    }
+
+    #[test]
+    fn from_bytes_rejects_a_header_claiming_an_absurd_element_count() {
+        // Header declares far more spans than the buffer could possibly
+        // hold; this must not overflow or panic while computing the
+        // span table/storage bounds.
+        let bytes = [0xffu8; 16];
+        assert!(SubStr::<u8>::from_bytes(&bytes).is_err());
+        assert!(SubStrRef::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn from_bytes_rejects_a_span_not_on_a_char_boundary() {
+        let mut bytes = built(&["héllo"]).to_bytes();
+        // "héllo" has a 2-byte 'é' at storage offset 1..3;
+        // shorten the one span to length 2 so it ends mid-char instead
+        // of on a char boundary.
+        let len_field = HEADER_LEN + 4;
+        bytes[len_field..len_field + 8].copy_from_slice(&2u64.to_le_bytes());
+        assert!(matches!(
+            SubStr::<u8>::from_bytes(&bytes),
+            Err(Error::InvalidData(_))
+        ));
+        assert!(matches!(
+            SubStrRef::from_bytes(&bytes),
+            Err(Error::InvalidData(_))
+        ));
+    }
+}